@@ -1,23 +1,73 @@
 use juniper::{FieldResult, RootNode};
 
 use crate::db::Context;
-use crate::models::graphql::Recipe;
-use crate::repository::RecipeRepository;
+use crate::models::graphql::{NewRecipe, Recipe};
+use crate::repository::{GroupRepository, RecipeRepository, UserRepository};
+
+/// Languages a recipe or ingredient can be translated into. `En` doubles as
+/// the fallback translation when a recipe has no row for the requested language.
+#[derive(juniper::GraphQLEnum, Clone, Copy, PartialEq)]
+pub enum Lang {
+    En,
+    Nl,
+    Fr,
+}
+
+impl Lang {
+    /// The ISO-639-1 code stored in the `lang` column of the translation tables.
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Nl => "nl",
+            Lang::Fr => "fr",
+        }
+    }
+}
+
+/// Outcome of a `register` mutation: either the account was created, or it
+/// clashed with an existing username or email.
+#[derive(juniper::GraphQLEnum, Clone, Copy, PartialEq)]
+pub enum RegistrationResult {
+    Ok,
+    UsernameTaken,
+    EmailTaken,
+}
 
 pub struct Query;
 
 #[juniper::object(Context = Context)]
 impl Query {
-    fn recipe_by_name(ctx: &Context, name: String) -> FieldResult<Option<Recipe>> {
-        RecipeRepository::get_recipe_by_name(&ctx.pool.get().unwrap(), &name)
+    fn recipe_by_name(ctx: &Context, name: String, lang: Option<Lang>) -> FieldResult<Option<Recipe>> {
+        RecipeRepository::get_recipe_by_name(&ctx.pool.get().unwrap(), &name, lang)
     }
 
-    fn recipes_by_ingredient(ctx: &Context, name: String) -> FieldResult<Vec<Recipe>> {
-        RecipeRepository::get_recipes_by_ingredient_name(&ctx.pool.get().unwrap(), &name)
+    fn recipes_by_ingredient(ctx: &Context, name: String, lang: Option<Lang>) -> FieldResult<Vec<Recipe>> {
+        RecipeRepository::get_recipes_by_ingredient_name(&ctx.pool.get().unwrap(), &name, lang)
     }
 
-    fn recipes_by_ingredients(ctx: &Context, names: Vec<String>) -> FieldResult<Vec<Recipe>> {
-        RecipeRepository::get_recipes_by_ingredient_names(&ctx.pool.get().unwrap(), &names)
+    fn recipes_by_ingredients(ctx: &Context, names: Vec<String>, lang: Option<Lang>) -> FieldResult<Vec<Recipe>> {
+        RecipeRepository::get_recipes_by_ingredient_names(&ctx.pool.get().unwrap(), &names, lang)
+    }
+
+    fn resolved_recipe(ctx: &Context, name: String) -> FieldResult<Recipe> {
+        RecipeRepository::resolve_recipe(&ctx.pool.get().unwrap(), &name)
+    }
+
+    fn my_recipes(ctx: &Context) -> FieldResult<Vec<Recipe>> {
+        RecipeRepository::get_recipes_by_user(
+            &ctx.pool.get().unwrap(),
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn group_recipes(ctx: &Context, group_name: String) -> FieldResult<Vec<Recipe>> {
+        GroupRepository::get_group_recipes(
+            &ctx.pool.get().unwrap(),
+            &group_name,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
     }
 }
 
@@ -25,6 +75,69 @@ pub struct Mutation;
 
 #[juniper::object(Context = Context)]
 impl Mutation {
+    fn create_recipe(ctx: &Context, recipe: NewRecipe) -> FieldResult<Recipe> {
+        RecipeRepository::create_recipe(
+            &ctx.pool.get().unwrap(),
+            recipe,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn update_recipe(ctx: &Context, recipe: NewRecipe) -> FieldResult<Recipe> {
+        RecipeRepository::update_recipe(
+            &ctx.pool.get().unwrap(),
+            recipe,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn delete_recipe(ctx: &Context, name: String) -> FieldResult<bool> {
+        RecipeRepository::delete_recipe(
+            &ctx.pool.get().unwrap(),
+            &name,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn register(ctx: &Context, username: String, email: String, password: String) -> FieldResult<RegistrationResult> {
+        UserRepository::register(&ctx.pool.get().unwrap(), &username, &email, &password)
+    }
+
+    fn validate_account(ctx: &Context, token: String) -> FieldResult<bool> {
+        UserRepository::validate_account(&ctx.pool.get().unwrap(), &token)
+    }
+
+    fn create_group(ctx: &Context, name: String) -> FieldResult<bool> {
+        GroupRepository::create_group(
+            &ctx.pool.get().unwrap(),
+            &name,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn add_member(ctx: &Context, group_name: String, username: String) -> FieldResult<bool> {
+        GroupRepository::add_member(
+            &ctx.pool.get().unwrap(),
+            &group_name,
+            &username,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
+
+    fn share_recipe(ctx: &Context, group_name: String, recipe_name: String) -> FieldResult<bool> {
+        GroupRepository::share_recipe(
+            &ctx.pool.get().unwrap(),
+            &group_name,
+            &recipe_name,
+            ctx.username.as_ref().map(String::as_str),
+            ctx.session_token.as_ref().map(String::as_str),
+        )
+    }
 }
 
 pub fn schema() -> RootNode<'static, Query, Mutation> {
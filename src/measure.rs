@@ -0,0 +1,226 @@
+//! Typed measurement units for ingredient quantities.
+//!
+//! `qty` used to be an opaque `String` threaded through `NewRecipeIngredient`
+//! and `RecipeIngredient`. Mutations still take it as text in
+//! `NewRecipeIngredient`; it is parsed into a [`Measure`], which replaces the
+//! free-form text with a structured amount + unit pair so quantities can be
+//! normalised to a common
+//! base unit, summed when aggregating a shopping list, and scaled to a
+//! different number of servings without string surgery.
+
+use juniper::GraphQLEnum;
+
+/// The unit a [`Measure`] is expressed in. Masses and volumes each share a
+/// base unit (grams and millilitres) so they can be summed and compared.
+#[derive(GraphQLEnum, Clone, Copy, PartialEq, Debug)]
+pub enum Unit {
+    Gram,
+    Kilogram,
+    Milliliter,
+    Liter,
+    Count,
+    Pinch,
+}
+
+/// The physical family a unit belongs to; only measures within the same family
+/// can be added together.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Family {
+    Mass,
+    Volume,
+    Count,
+    Pinch,
+}
+
+impl Unit {
+    /// How many base units (grams for mass, millilitres for volume) one of this
+    /// unit represents.
+    fn base_factor(self) -> u32 {
+        match self {
+            Unit::Gram => 1,
+            Unit::Kilogram => 1000,
+            Unit::Milliliter => 1,
+            Unit::Liter => 1000,
+            Unit::Count => 1,
+            Unit::Pinch => 1,
+        }
+    }
+
+    fn family(self) -> Family {
+        match self {
+            Unit::Gram | Unit::Kilogram => Family::Mass,
+            Unit::Milliliter | Unit::Liter => Family::Volume,
+            Unit::Count => Family::Count,
+            Unit::Pinch => Family::Pinch,
+        }
+    }
+
+    /// The canonical base unit for this unit's family.
+    fn base_unit(self) -> Unit {
+        match self.family() {
+            Family::Mass => Unit::Gram,
+            Family::Volume => Unit::Milliliter,
+            Family::Count => Unit::Count,
+            Family::Pinch => Unit::Pinch,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Liter => "l",
+            Unit::Count => "",
+            Unit::Pinch => "pinch",
+        }
+    }
+
+    /// The stable code stored in the `unit` column. Unlike [`suffix`], every
+    /// variant has a distinct, non-empty code so a row round-trips unambiguously
+    /// (the empty suffix of `Count` would otherwise clash with a missing unit).
+    pub fn code(self) -> &'static str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Liter => "l",
+            Unit::Count => "count",
+            Unit::Pinch => "pinch",
+        }
+    }
+
+    /// Parse a [`code`](Self::code) back into a [`Unit`].
+    pub fn from_code(code: &str) -> Option<Unit> {
+        match code {
+            "g" => Some(Unit::Gram),
+            "kg" => Some(Unit::Kilogram),
+            "ml" => Some(Unit::Milliliter),
+            "l" => Some(Unit::Liter),
+            "count" => Some(Unit::Count),
+            "pinch" => Some(Unit::Pinch),
+            _ => None,
+        }
+    }
+}
+
+/// A quantity of an ingredient: an amount paired with its [`Unit`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Measure {
+    amount: u32,
+    unit: Unit,
+}
+
+#[juniper::object]
+impl Measure {
+    /// The amount in the measure's own unit (e.g. `2` for `2kg`).
+    fn amount(&self) -> i32 {
+        self.amount as i32
+    }
+
+    /// The unit this measure is expressed in.
+    fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    /// The amount converted to the family's base unit (grams or millilitres),
+    /// for display alongside the native amount.
+    fn base_amount(&self) -> i32 {
+        self.base_amount() as i32
+    }
+}
+
+impl Measure {
+    pub fn new(amount: u32, unit: Unit) -> Self {
+        Measure { amount, unit }
+    }
+
+    /// Build a measure from the stored `amount`/`unit` columns, falling back to
+    /// parsing the legacy `qty` string when a row predates the typed columns
+    /// (either column still `NULL`, or an unrecognised unit code).
+    pub fn from_columns(amount: Option<i32>, unit: Option<&str>, qty: &str) -> Measure {
+        match (amount, unit.and_then(Unit::from_code)) {
+            (Some(amount), Some(unit)) => Measure::new(amount.max(0) as u32, unit),
+            _ => Measure::parse(qty),
+        }
+    }
+
+    /// The amount as stored in the `amount` column.
+    pub fn amount_column(&self) -> i32 {
+        self.amount as i32
+    }
+
+    /// The unit code as stored in the `unit` column.
+    pub fn unit_column(&self) -> String {
+        self.unit.code().to_string()
+    }
+
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    pub fn family(&self) -> Family {
+        self.unit.family()
+    }
+
+    /// The amount expressed in the family's base unit.
+    pub fn base_amount(&self) -> u64 {
+        self.amount as u64 * self.unit.base_factor() as u64
+    }
+
+    /// Parse the legacy `qty` string (e.g. `"200g"`, `"2kg"`, `"500ml"`, `"3"`,
+    /// `"pinch"`). Unrecognised text falls back to a `Count` of `1` so the
+    /// ingredient still appears on the list.
+    pub fn parse(qty: &str) -> Measure {
+        let qty = qty.trim();
+        if qty.eq_ignore_ascii_case("pinch") {
+            return Measure::new(1, Unit::Pinch);
+        }
+
+        let split_at = qty
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or_else(|| qty.len());
+        let (amount, suffix) = qty.split_at(split_at);
+        let amount = amount.parse::<u32>().unwrap_or(1);
+        let unit = match suffix.trim().to_ascii_lowercase().as_str() {
+            "g" => Unit::Gram,
+            "kg" => Unit::Kilogram,
+            "ml" => Unit::Milliliter,
+            "l" => Unit::Liter,
+            "" => Unit::Count,
+            _ => Unit::Count,
+        };
+        Measure::new(amount, unit)
+    }
+
+    /// Render back to the `qty` string format stored in the database.
+    pub fn to_qty(&self) -> String {
+        match self.unit {
+            Unit::Pinch => "pinch".to_string(),
+            _ => format!("{}{}", self.amount, self.unit.suffix()),
+        }
+    }
+
+    /// Multiply the amount by `factor`, keeping the same unit.
+    pub fn scaled(&self, factor: u32) -> Measure {
+        Measure::new(self.amount.saturating_mul(factor), self.unit)
+    }
+
+    /// Multiply by a fractional `factor` (used for `servings_multiplier` along
+    /// a sub-recipe path), returning the result in the family's base unit so it
+    /// can be summed with other measures.
+    pub fn scale_by(&self, factor: f64) -> Measure {
+        let base = (self.base_amount() as f64 * factor).round() as u32;
+        Measure::new(base, self.unit.base_unit())
+    }
+
+    /// Add `other` to `self` when they share a family, returning the sum in the
+    /// family's base unit. Returns `None` when the units are incompatible.
+    pub fn add(&self, other: &Measure) -> Option<Measure> {
+        if self.family() != other.family() {
+            return None;
+        }
+        let total = (self.base_amount() + other.base_amount()) as u32;
+        Some(Measure::new(total, self.unit.base_unit()))
+    }
+}
@@ -0,0 +1,99 @@
+//! The GraphQL-facing recipe types. These mirror the `postgres` rows but carry
+//! the localised names and the typed [`Measure`] the schema exposes, keeping
+//! the raw database structs out of the public schema.
+
+use crate::measure::Measure;
+use crate::models::postgres as pg;
+
+/// A single ingredient line on a recipe: its (possibly localised) name and a
+/// typed [`Measure`] read from the stored `amount`/`unit` columns.
+#[derive(Clone)]
+pub struct RecipeIngredient {
+    pub name: String,
+    pub measure: Measure,
+}
+
+#[juniper::object]
+impl RecipeIngredient {
+    /// The ingredient's name.
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The quantity as a typed measure rather than an opaque string.
+    fn measure(&self) -> Measure {
+        self.measure
+    }
+}
+
+/// A recipe and its ingredient lines as returned by the schema.
+#[derive(Clone)]
+pub struct Recipe {
+    pub name: String,
+    pub ingredients: Vec<RecipeIngredient>,
+}
+
+#[juniper::object]
+impl Recipe {
+    /// The recipe's name.
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The recipe's ingredients with their typed measures.
+    fn ingredients(&self) -> &[RecipeIngredient] {
+        &self.ingredients
+    }
+
+    /// The same recipe with every ingredient's [`Measure`] multiplied by
+    /// `servings`, so a caller can pull a scaled shopping list inline without a
+    /// second query.
+    fn scaled_to(&self, servings: i32) -> Recipe {
+        let factor = servings.max(0) as u32;
+        Recipe {
+            name: self.name.clone(),
+            ingredients: self
+                .ingredients
+                .iter()
+                .map(|line| RecipeIngredient {
+                    name: line.name.clone(),
+                    measure: line.measure.scaled(factor),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Recipe {
+    /// Build a GraphQL recipe from a database row and its joined ingredient
+    /// rows, reading each quantity from the stored amount+unit pair.
+    pub fn from_pg(
+        recipe: &pg::Recipe,
+        ingredients: &[(pg::RecipeIngredient, pg::Ingredient)],
+    ) -> Recipe {
+        Recipe {
+            name: recipe.name.clone(),
+            ingredients: ingredients
+                .iter()
+                .map(|(ri, ingredient)| RecipeIngredient {
+                    name: ingredient.name.clone(),
+                    measure: Measure::from_columns(ri.amount, ri.unit.as_ref().map(String::as_str), &ri.qty),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An ingredient line supplied to the `createRecipe`/`updateRecipe` mutations.
+#[derive(juniper::GraphQLInputObject, Clone)]
+pub struct NewRecipeIngredient {
+    pub name: String,
+    pub qty: String,
+}
+
+/// A recipe supplied to the `createRecipe`/`updateRecipe` mutations.
+#[derive(juniper::GraphQLInputObject, Clone)]
+pub struct NewRecipe {
+    pub name: String,
+    pub ingredients: Vec<NewRecipeIngredient>,
+}
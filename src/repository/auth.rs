@@ -0,0 +1,50 @@
+//! Shared repository helpers: the diesel-result adaptor and the single source
+//! of truth for session authentication, used by every repository that gates a
+//! mutation on the current session user.
+
+use chrono::Utc;
+use diesel::PgConnection;
+use diesel::prelude::*;
+use juniper::{FieldError, FieldResult, Value};
+
+use crate::models::postgres as pg;
+use crate::schema::*;
+
+pub(crate) type PgResult<T> = Result<T, diesel::result::Error>;
+
+pub(crate) fn as_field_result<T>(pg_result: PgResult<T>) -> FieldResult<T> {
+    pg_result.map_err(|e| FieldError::from(e))
+}
+
+/// Resolve the user behind the current session, rejecting callers who are not
+/// logged in. `route::get_context` has already verified the cookie signature,
+/// so here we only re-check that the `session_token` still maps to an
+/// unexpired session owned by `username` before trusting a mutation.
+pub(crate) fn authenticate(
+    conn: &PgConnection,
+    username: Option<&str>,
+    session_token: Option<&str>,
+) -> FieldResult<pg::User> {
+    let (username, session_token) = match (username, session_token) {
+        (Some(username), Some(session_token)) => (username, session_token),
+        _ => return Err(FieldError::new("Not authenticated", Value::null())),
+    };
+
+    let session = as_field_result(
+        sessions::table
+            .filter(sessions::token.eq(session_token))
+            .filter(sessions::expires_at.gt(Utc::now().naive_utc()))
+            .get_result::<pg::Session>(conn)
+            .optional()
+    )?
+    .ok_or_else(|| FieldError::new("Not authenticated", Value::null()))?;
+
+    as_field_result(
+        users::table
+            .find(session.user_id)
+            .filter(users::username.eq(username))
+            .get_result::<pg::User>(conn)
+            .optional()
+    )?
+    .ok_or_else(|| FieldError::new("Not authenticated", Value::null()))
+}
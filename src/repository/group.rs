@@ -0,0 +1,216 @@
+use diesel::PgConnection;
+use diesel::prelude::*;
+use juniper::{FieldError, FieldResult, Value};
+
+use crate::db::PostgresPool;
+use crate::models::{graphql as gql, postgres as pg};
+use crate::repository::auth::{as_field_result, authenticate};
+use crate::schema::*;
+
+pub struct GroupRepository {
+    pub pool: PostgresPool
+}
+
+impl GroupRepository {
+    /// Create a group owned by the session user, enrolling them as its first
+    /// member. A name already in use is rejected.
+    pub fn create_group(
+        conn: &PgConnection,
+        name: &str,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<bool> {
+        let user = authenticate(conn, username, session_token)?;
+
+        conn.transaction(|| {
+            let group: pg::Group = as_field_result(
+                diesel::insert_into(groups::table)
+                    .values(pg::NewGroup {
+                        name: name.to_string(),
+                        owner_id: user.id,
+                    })
+                    .get_result(conn)
+            )?;
+
+            as_field_result(
+                diesel::insert_into(group_members::table)
+                    .values(pg::NewGroupMember {
+                        group_id: group.id,
+                        user_id: user.id,
+                    })
+                    .execute(conn)
+            )?;
+
+            Ok(true)
+        })
+    }
+
+    /// Add a user to a group. Only the group owner may enrol new members; a
+    /// repeated add is a no-op.
+    pub fn add_member(
+        conn: &PgConnection,
+        group_name: &str,
+        member_username: &str,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<bool> {
+        let user = authenticate(conn, username, session_token)?;
+        let group = Self::group_by_name(conn, group_name)?;
+
+        if group.owner_id != user.id {
+            return Err(FieldError::new(
+                format!("Not allowed to manage group {}", group_name),
+                Value::null(),
+            ));
+        }
+
+        let member = as_field_result(
+            users::table
+                .filter(users::username.eq(member_username))
+                .get_result::<pg::User>(conn)
+                .optional()
+        )?
+        .ok_or_else(|| FieldError::new(
+            format!("No user with name {}", member_username),
+            Value::null(),
+        ))?;
+
+        as_field_result(
+            diesel::insert_into(group_members::table)
+                .values(pg::NewGroupMember {
+                    group_id: group.id,
+                    user_id: member.id,
+                })
+                .on_conflict((group_members::group_id, group_members::user_id))
+                .do_nothing()
+                .execute(conn)
+        )?;
+
+        Ok(true)
+    }
+
+    /// Share a recipe the session user owns with a group they belong to.
+    pub fn share_recipe(
+        conn: &PgConnection,
+        group_name: &str,
+        recipe_name: &str,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<bool> {
+        let user = authenticate(conn, username, session_token)?;
+        let group = Self::group_by_name(conn, group_name)?;
+        Self::require_membership(conn, &group, user.id)?;
+
+        let recipe = as_field_result(
+            recipes::table
+                .filter(recipes::name.eq(recipe_name))
+                .get_result::<pg::Recipe>(conn)
+                .optional()
+        )?
+        .ok_or_else(|| FieldError::new(
+            format!("No recipe with name {}", recipe_name),
+            Value::null(),
+        ))?;
+
+        if recipe.user_id != Some(user.id) {
+            return Err(FieldError::new(
+                format!("Not allowed to share recipe {}", recipe_name),
+                Value::null(),
+            ));
+        }
+
+        as_field_result(
+            diesel::insert_into(recipe_shares::table)
+                .values(pg::NewRecipeShare {
+                    group_id: group.id,
+                    recipe_id: recipe.id,
+                })
+                .on_conflict((recipe_shares::group_id, recipe_shares::recipe_id))
+                .do_nothing()
+                .execute(conn)
+        )?;
+
+        Ok(true)
+    }
+
+    /// List the recipes shared with `group_name`. Recipes the session user owns
+    /// but has not shared with this group are not included. Non-members are
+    /// rejected.
+    pub fn get_group_recipes(
+        conn: &PgConnection,
+        group_name: &str,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<Vec<gql::Recipe>> {
+        let user = authenticate(conn, username, session_token)?;
+        let group = Self::group_by_name(conn, group_name)?;
+        Self::require_membership(conn, &group, user.id)?;
+
+        let shared_ids: Vec<i32> = as_field_result(
+            recipe_shares::table
+                .filter(recipe_shares::group_id.eq(group.id))
+                .select(recipe_shares::recipe_id)
+                .get_results(conn)
+        )?;
+
+        // Scope strictly to the recipes shared with *this* group; a recipe the
+        // caller owns but never shared here must not leak in just because they
+        // are a member.
+        let pg_recipes: Vec<pg::Recipe> = as_field_result(
+            recipes::table
+                .filter(recipes::id.eq_any(&shared_ids))
+                .get_results::<pg::Recipe>(conn)
+        )?;
+
+        let pg_recipes_with_ingredients = pg::RecipeIngredient::belonging_to(&pg_recipes)
+            .inner_join(ingredients::table)
+            .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)?
+            .grouped_by(&pg_recipes);
+
+        Ok(pg_recipes
+            .iter()
+            .zip(pg_recipes_with_ingredients)
+            .map(|(r, ings)| gql::Recipe::from_pg(r, &ings))
+            .collect())
+    }
+
+    fn group_by_name(conn: &PgConnection, group_name: &str) -> FieldResult<pg::Group> {
+        as_field_result(
+            groups::table
+                .filter(groups::name.eq(group_name))
+                .get_result::<pg::Group>(conn)
+                .optional()
+        )?
+        .ok_or_else(|| FieldError::new(
+            format!("No group with name {}", group_name),
+            Value::null(),
+        ))
+    }
+
+    /// Assert `user_id` belongs to `group`, mapping a non-member to a
+    /// `FieldError` so a group's contents never leak to outsiders.
+    fn require_membership(
+        conn: &PgConnection,
+        group: &pg::Group,
+        user_id: i32,
+    ) -> FieldResult<()> {
+        let member = as_field_result(
+            group_members::table
+                .filter(group_members::group_id.eq(group.id))
+                .filter(group_members::user_id.eq(user_id))
+                .select(group_members::id)
+                .get_result::<i32>(conn)
+                .optional()
+        )?
+        .is_some();
+
+        if !member {
+            return Err(FieldError::new(
+                format!("Not a member of group {}", group.name),
+                Value::null(),
+            ));
+        }
+
+        Ok(())
+    }
+}
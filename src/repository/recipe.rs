@@ -1,16 +1,57 @@
+use std::collections::HashMap;
+
 use diesel::pg::upsert::excluded;
 use diesel::PgConnection;
 use diesel::prelude::*;
 use juniper::{FieldError, FieldResult, Value};
 
 use crate::db::PostgresPool;
+use crate::graphql::Lang;
+use crate::measure::Measure;
 use crate::models::{graphql as gql, postgres as pg};
+use crate::repository::auth::{as_field_result, authenticate, PgResult};
 use crate::schema::*;
 
-type PgResult<T> = Result<T, diesel::result::Error>;
+/// A localised ingredient name, keyed by ingredient and `lang` code. The full
+/// row is loaded so the column order matches the table; only `ingredient_id`,
+/// `lang` and `name` are read back.
+#[derive(Queryable)]
+#[allow(dead_code)]
+struct IngredientTranslation {
+    id: i32,
+    ingredient_id: i32,
+    lang: String,
+    name: String,
+}
+
+/// Tri-colour marking used while walking the recipe dependency graph.
+/// `Gray` marks a recipe currently on the recursion stack (an edge back to it
+/// is a cycle), `Black` a recipe whose whole subtree has been aggregated.
+#[derive(Clone, Copy, PartialEq)]
+enum Color {
+    Gray,
+    Black,
+}
+
+/// A `parent -> child` edge in the recipe dependency graph, scaling the child's
+/// ingredients by `servings_multiplier` when it is pulled into the parent.
+#[derive(Queryable, Identifiable, Associations)]
+#[table_name = "recipe_dependencies"]
+struct RecipeDependency {
+    id: i32,
+    parent_recipe_id: i32,
+    child_recipe_id: i32,
+    servings_multiplier: f64,
+}
 
-fn as_field_result<T>(pg_result: PgResult<T>) -> FieldResult<T> {
-    pg_result.map_err(|e| FieldError::from(e))
+/// From `(lang_code, name)` rows, pick the name for `lang`, else the English
+/// fallback, else nothing (keeping the canonical name).
+fn pick_translation(candidates: &[(String, String)], lang: Lang) -> Option<String> {
+    candidates
+        .iter()
+        .find(|(code, _)| code == lang.code())
+        .or_else(|| candidates.iter().find(|(code, _)| code == Lang::En.code()))
+        .map(|(_, name)| name.clone())
 }
 
 pub struct RecipeRepository {
@@ -21,6 +62,7 @@ impl RecipeRepository {
     pub fn get_recipe_by_name(
         conn: &PgConnection,
         recipe_name: &str,
+        lang: Option<Lang>,
     ) -> FieldResult<Option<gql::Recipe>> {
         let recipe = as_field_result(
             recipes::table
@@ -30,77 +72,174 @@ impl RecipeRepository {
         )?;
 
         match recipe {
-            Some(r) => as_field_result(
-                pg::RecipeIngredient::belonging_to(&r)
-                    .inner_join(ingredients::table)
-                    .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)
-            ).map(|ings| {
-                Some(gql::Recipe::from_pg(&r, &ings))
-            }),
+            Some(r) => {
+                let ings = as_field_result(
+                    pg::RecipeIngredient::belonging_to(&r)
+                        .inner_join(ingredients::table)
+                        .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)
+                )?;
+                let (r, ings) = Self::localize(conn, r, ings, lang)?;
+                Ok(Some(gql::Recipe::from_pg(&r, &ings)))
+            }
             None => Err(FieldError::new(format!("No recipe with name {}", recipe_name), Value::null()))
         }
     }
 
+    /// Look up the ids of every ingredient whose canonical name *or* any of its
+    /// translations matches `name`, so a search for "flour" and "farine" hit
+    /// the same ingredient.
+    fn ingredient_ids_by_name(conn: &PgConnection, name: &str) -> PgResult<Vec<i32>> {
+        let mut ids: Vec<i32> = ingredients::table
+            .filter(ingredients::name.eq(name))
+            .select(ingredients::id)
+            .get_results(conn)?;
+
+        let mut translated: Vec<i32> = ingredient_translations::table
+            .filter(ingredient_translations::name.eq(name))
+            .select(ingredient_translations::ingredient_id)
+            .get_results(conn)?;
+
+        ids.append(&mut translated);
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Replace the recipe name and its ingredient names with the translations
+    /// for `lang`, falling back to the English translation and finally to the
+    /// canonical name when a row is missing. A `None` language keeps the
+    /// canonical names untouched.
+    fn localize(
+        conn: &PgConnection,
+        mut recipe: pg::Recipe,
+        mut ingredients: Vec<(pg::RecipeIngredient, pg::Ingredient)>,
+        lang: Option<Lang>,
+    ) -> FieldResult<(pg::Recipe, Vec<(pg::RecipeIngredient, pg::Ingredient)>)> {
+        let lang = match lang {
+            Some(lang) => lang,
+            None => return Ok((recipe, ingredients)),
+        };
+
+        let recipe_names: Vec<(String, String)> = as_field_result(
+            recipe_translations::table
+                .filter(recipe_translations::recipe_id.eq(recipe.id))
+                .filter(recipe_translations::lang.eq_any(&[lang.code(), Lang::En.code()]))
+                .select((recipe_translations::lang, recipe_translations::name))
+                .get_results(conn)
+        )?;
+        if let Some(name) = pick_translation(&recipe_names, lang) {
+            recipe.name = name;
+        }
+
+        let ingredient_ids: Vec<i32> = ingredients.iter().map(|(_, i)| i.id).collect();
+        let translations: Vec<IngredientTranslation> = as_field_result(
+            ingredient_translations::table
+                .filter(ingredient_translations::ingredient_id.eq_any(&ingredient_ids))
+                .filter(ingredient_translations::lang.eq_any(&[lang.code(), Lang::En.code()]))
+                .get_results(conn)
+        )?;
+        let mut by_ingredient: HashMap<i32, Vec<(String, String)>> = HashMap::new();
+        for t in translations {
+            by_ingredient.entry(t.ingredient_id).or_default().push((t.lang, t.name));
+        }
+
+        for (_, ingredient) in ingredients.iter_mut() {
+            if let Some(candidates) = by_ingredient.get(&ingredient.id) {
+                if let Some(name) = pick_translation(candidates, lang) {
+                    ingredient.name = name;
+                }
+            }
+        }
+
+        Ok((recipe, ingredients))
+    }
+
     pub fn get_recipes_by_ingredient_name(
         conn: &PgConnection,
         ingredient_name: &str,
+        lang: Option<Lang>,
     ) -> FieldResult<Vec<gql::Recipe>> {
-        let pg_result = as_field_result(
-            ingredients::table
-                .filter(ingredients::name.eq(ingredient_name))
-                .get_result::<pg::Ingredient>(conn)
-                .optional()
-        )?;
+        let ingredient_ids = as_field_result(Self::ingredient_ids_by_name(conn, ingredient_name))?;
 
-        let pg_recipes = match pg_result {
-            Some(ing) => as_field_result(
-                pg::RecipeIngredient::belonging_to(&ing)
-                    .inner_join(recipes::table)
-                    .select(recipes::all_columns)
-                    .get_results::<pg::Recipe>(conn)
-            )?,
-            None => return Err(FieldError::new(
+        if ingredient_ids.is_empty() {
+            return Err(FieldError::new(
                 format!("No ingredient with name {}", ingredient_name),
                 Value::null(),
-            ))
-        };
+            ));
+        }
+
+        let pg_ingredients: Vec<pg::Ingredient> = as_field_result(
+            ingredients::table
+                .filter(ingredients::id.eq_any(&ingredient_ids))
+                .get_results::<pg::Ingredient>(conn)
+        )?;
+
+        let mut pg_recipes = as_field_result(
+            pg::RecipeIngredient::belonging_to(&pg_ingredients)
+                .inner_join(recipes::table)
+                .select(recipes::all_columns)
+                .get_results::<pg::Recipe>(conn)
+        )?;
+        // A single name can expand to several ingredient ids, so the same
+        // recipe may appear once per matching ingredient; collapse duplicates.
+        pg_recipes.sort_by_key(|r| r.id);
+        pg_recipes.dedup_by_key(|r| r.id);
 
         let pg_recipes_with_ingredients = pg::RecipeIngredient::belonging_to(&pg_recipes)
             .inner_join(ingredients::table)
             .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)?
             .grouped_by(&pg_recipes);
 
-        let found_recipes = pg_recipes
-            .iter()
+        pg_recipes
+            .into_iter()
             .zip(pg_recipes_with_ingredients)
-            .map(|(r, ings)| gql::Recipe::from_pg(&r, &ings))
-            .collect();
-
-        Ok(found_recipes)
+            .map(|(r, ings)| {
+                let (r, ings) = Self::localize(conn, r, ings, lang)?;
+                Ok(gql::Recipe::from_pg(&r, &ings))
+            })
+            .collect()
     }
 
     pub fn get_recipes_by_ingredient_names(
         conn: &PgConnection,
         ingredient_names: &Vec<String>,
+        lang: Option<Lang>,
     ) -> FieldResult<Vec<gql::Recipe>> {
+        // Resolve each requested name to the set of ingredient ids it matches
+        // (across every language). A recipe qualifies when it contains at least
+        // one ingredient from each requested name's set.
+        let mut ids_per_name: Vec<Vec<i32>> = Vec::with_capacity(ingredient_names.len());
+        for name in ingredient_names {
+            let ids = as_field_result(Self::ingredient_ids_by_name(conn, name))?;
+            if ids.is_empty() {
+                return Err(FieldError::new(
+                    format!("No ingredient with name {}", name),
+                    Value::null(),
+                ));
+            }
+            ids_per_name.push(ids);
+        }
+
+        let all_ids: Vec<i32> = {
+            let mut ids: Vec<i32> = ids_per_name.iter().flatten().cloned().collect();
+            ids.sort();
+            ids.dedup();
+            ids
+        };
+
         let pg_ingredients: Vec<pg::Ingredient> = as_field_result(
             ingredients::table
-                .filter(ingredients::name.eq_any(ingredient_names))
+                .filter(ingredients::id.eq_any(&all_ids))
                 .get_results::<pg::Ingredient>(conn)
         )?;
 
-        if ingredient_names.len() != pg_ingredients.len() {
-            return Err(FieldError::new(
-                format!("Not all ingredients found. Wanted: {:?}. Found: {:?}", ingredient_names, pg_ingredients),
-                Value::null(),
-            ));
-        }
-
-        let pg_recipes: Vec<pg::Recipe> = as_field_result(pg::RecipeIngredient::belonging_to(&pg_ingredients)
+        let mut pg_recipes: Vec<pg::Recipe> = as_field_result(pg::RecipeIngredient::belonging_to(&pg_ingredients)
             .inner_join(recipes::table)
             .select(recipes::all_columns)
             .get_results::<pg::Recipe>(conn)
         )?;
+        pg_recipes.sort_by_key(|r| r.id);
+        pg_recipes.dedup_by_key(|r| r.id);
 
         let pg_recipes_with_ingredients: Vec<(Vec<(pg::RecipeIngredient, pg::Ingredient)>, pg::Recipe)> =
             pg::RecipeIngredient::belonging_to(&pg_recipes)
@@ -110,20 +249,166 @@ impl RecipeRepository {
                 .into_iter()
                 .zip(pg_recipes)
                 .filter(|(ings, _)| {
-                    let found: Vec<&pg::Ingredient> = ings.into_iter().map(|(_, i)| i).collect();
-                    pg_ingredients.iter().all(|ing| found.contains(&ing))
+                    let found: Vec<i32> = ings.iter().map(|(_, i)| i.id).collect();
+                    ids_per_name
+                        .iter()
+                        .all(|ids| ids.iter().any(|id| found.contains(id)))
                 })
                 .collect();
 
-        Ok(
-            pg_recipes_with_ingredients
-                .iter()
-                .map(|(ings, r)| gql::Recipe::from_pg(&r, &ings))
-                .collect()
-        )
+        pg_recipes_with_ingredients
+            .into_iter()
+            .map(|(ings, r)| {
+                let (r, ings) = Self::localize(conn, r, ings, lang)?;
+                Ok(gql::Recipe::from_pg(&r, &ings))
+            })
+            .collect()
     }
 
-    pub fn insert_recipe(conn: &PgConnection, recipe: gql::NewRecipe) -> FieldResult<gql::Recipe> {
+    /// Flatten a recipe and every recipe it references through the
+    /// `recipe_dependencies` table into a single, de-duplicated shopping list.
+    ///
+    /// The dependency graph is walked depth-first while colouring each recipe
+    /// white/gray/black: a recipe is marked gray on entry and black on exit,
+    /// and an edge that reaches a gray recipe is reported as a cycle naming the
+    /// offending path. Each recipe's own contribution (its subtree aggregated
+    /// at multiplier `1.0`) is memoised the first time it is finished, so a
+    /// diamond in the graph is aggregated once and then scaled by each incoming
+    /// edge's `servings_multiplier` rather than re-walked per parent. Duplicate
+    /// ingredients are merged by summing their amounts.
+    pub fn resolve_recipe(
+        conn: &PgConnection,
+        recipe_name: &str,
+    ) -> FieldResult<gql::Recipe> {
+        let root = as_field_result(
+            recipes::table
+                .filter(recipes::name.eq(recipe_name))
+                .get_result::<pg::Recipe>(conn)
+                .optional()
+        )?
+        .ok_or_else(|| FieldError::new(
+            format!("No recipe with name {}", recipe_name),
+            Value::null(),
+        ))?;
+
+        let mut colors: HashMap<i32, Color> = HashMap::new();
+        let mut memo: HashMap<i32, HashMap<i32, (pg::Ingredient, Measure)>> = HashMap::new();
+        let mut path: Vec<String> = Vec::new();
+
+        let aggregated = Self::aggregate(conn, &root, &mut colors, &mut memo, &mut path)?;
+
+        let ingredients: Vec<(pg::RecipeIngredient, pg::Ingredient)> = aggregated
+            .into_iter()
+            .map(|(ingredient_id, (ingredient, measure))| {
+                let recipe_ingredient = pg::RecipeIngredient {
+                    recipe_id: root.id,
+                    ingredient_id,
+                    qty: measure.to_qty(),
+                    amount: Some(measure.amount_column()),
+                    unit: Some(measure.unit_column()),
+                };
+                (recipe_ingredient, ingredient)
+            })
+            .collect();
+
+        Ok(gql::Recipe::from_pg(&root, &ingredients))
+    }
+
+    /// Merge `measure` into `totals` under `ingredient`, summing compatible
+    /// measures and keeping the running total when the units belong to
+    /// different families and cannot be added.
+    fn merge_measure(
+        totals: &mut HashMap<i32, (pg::Ingredient, Measure)>,
+        ingredient: pg::Ingredient,
+        measure: Measure,
+    ) {
+        match totals.get_mut(&ingredient.id) {
+            Some((_, total)) => {
+                if let Some(sum) = total.add(&measure) {
+                    *total = sum;
+                }
+            }
+            None => {
+                totals.insert(ingredient.id, (ingredient, measure));
+            }
+        }
+    }
+
+    /// Aggregate `recipe`'s subtree into a `ingredient_id -> (ingredient, total)`
+    /// map at multiplier `1.0`, memoising the result so shared sub-recipes are
+    /// only ever walked once. A child subtree scales linearly, so an incoming
+    /// edge applies its `servings_multiplier` to the memoised total instead of
+    /// re-descending. The gray colour still marks recipes on the current stack,
+    /// turning a back-edge into a reported cycle.
+    fn aggregate(
+        conn: &PgConnection,
+        recipe: &pg::Recipe,
+        colors: &mut HashMap<i32, Color>,
+        memo: &mut HashMap<i32, HashMap<i32, (pg::Ingredient, Measure)>>,
+        path: &mut Vec<String>,
+    ) -> FieldResult<HashMap<i32, (pg::Ingredient, Measure)>> {
+        if let Some(cached) = memo.get(&recipe.id) {
+            return Ok(cached.clone());
+        }
+
+        colors.insert(recipe.id, Color::Gray);
+        path.push(recipe.name.clone());
+
+        let mut totals: HashMap<i32, (pg::Ingredient, Measure)> = HashMap::new();
+
+        let own_ingredients = as_field_result(
+            pg::RecipeIngredient::belonging_to(recipe)
+                .inner_join(ingredients::table)
+                .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)
+        )?;
+
+        for (recipe_ingredient, ingredient) in own_ingredients {
+            let measure = Measure::from_columns(
+                recipe_ingredient.amount,
+                recipe_ingredient.unit.as_ref().map(String::as_str),
+                &recipe_ingredient.qty,
+            );
+            Self::merge_measure(&mut totals, ingredient, measure);
+        }
+
+        let dependencies = as_field_result(
+            recipe_dependencies::table
+                .filter(recipe_dependencies::parent_recipe_id.eq(recipe.id))
+                .get_results::<RecipeDependency>(conn)
+        )?;
+
+        for dependency in dependencies {
+            let child = as_field_result(
+                recipes::table
+                    .find(dependency.child_recipe_id)
+                    .get_result::<pg::Recipe>(conn)
+            )?;
+
+            if let Some(Color::Gray) = colors.get(&child.id) {
+                path.push(child.name.clone());
+                return Err(FieldError::new(
+                    format!("Cyclic recipe dependency: {}", path.join(" -> ")),
+                    Value::null(),
+                ));
+            }
+
+            let child_totals = Self::aggregate(conn, &child, colors, memo, path)?;
+            for (_, (ingredient, measure)) in child_totals {
+                Self::merge_measure(&mut totals, ingredient, measure.scale_by(dependency.servings_multiplier));
+            }
+        }
+
+        path.pop();
+        colors.insert(recipe.id, Color::Black);
+        memo.insert(recipe.id, totals.clone());
+        Ok(totals)
+    }
+
+    pub fn insert_recipe(
+        conn: &PgConnection,
+        recipe: gql::NewRecipe,
+        owner: i32,
+    ) -> FieldResult<gql::Recipe> {
         conn.transaction(|| {
             let inserted_recipe: pg::Recipe = as_field_result(
                 diesel::insert_into(recipes::table)
@@ -134,6 +419,15 @@ impl RecipeRepository {
                     .get_result(conn)
             )?;
 
+            // Stamp the owner now the row id is known. Both callers
+            // (`create_recipe`/`update_recipe`) have already checked the
+            // session user may write this name before reaching here.
+            let inserted_recipe: pg::Recipe = as_field_result(
+                diesel::update(recipes::table.find(inserted_recipe.id))
+                    .set(recipes::user_id.eq(owner))
+                    .get_result(conn)
+            )?;
+
             let inserted_ingredients: Vec<pg::Ingredient> = as_field_result(
                 diesel::insert_into(ingredients::table)
                     .values(pg::NewIngredient::from_graphql_many(&recipe.ingredients))
@@ -147,10 +441,15 @@ impl RecipeRepository {
                 let new_recipe_ingredients: Vec<pg::NewRecipeIngredient> = inserted_ingredients
                     .iter()
                     .zip(recipe.ingredients.iter())
-                    .map(|(pg_i, gql_i)| pg::NewRecipeIngredient {
-                        ingredient_id: pg_i.id,
-                        recipe_id: inserted_recipe.id,
-                        qty: gql_i.qty.clone(),
+                    .map(|(pg_i, gql_i)| {
+                        let measure = Measure::parse(&gql_i.qty);
+                        pg::NewRecipeIngredient {
+                            ingredient_id: pg_i.id,
+                            recipe_id: inserted_recipe.id,
+                            qty: gql_i.qty.clone(),
+                            amount: Some(measure.amount_column()),
+                            unit: Some(measure.unit_column()),
+                        }
                     })
                     .collect();
 
@@ -159,11 +458,31 @@ impl RecipeRepository {
                         .values(new_recipe_ingredients)
                         .on_conflict((recipe_ingredients::recipe_id, recipe_ingredients::ingredient_id))
                         .do_update()
-                        .set(recipe_ingredients::qty.eq(excluded(recipe_ingredients::qty)))
+                        .set((
+                            recipe_ingredients::qty.eq(excluded(recipe_ingredients::qty)),
+                            recipe_ingredients::amount.eq(excluded(recipe_ingredients::amount)),
+                            recipe_ingredients::unit.eq(excluded(recipe_ingredients::unit)),
+                        ))
                         .get_results(conn)
                 )?
             };
 
+            // Drop ingredient lines that are no longer in the payload, so an
+            // `update_recipe` that removes an ingredient really removes it
+            // rather than leaving the stale row behind.
+            let kept_ingredient_ids: Vec<i32> = inserted_recipe_ingredients
+                .iter()
+                .map(|ri| ri.ingredient_id)
+                .collect();
+            as_field_result(
+                diesel::delete(
+                    recipe_ingredients::table
+                        .filter(recipe_ingredients::recipe_id.eq(inserted_recipe.id))
+                        .filter(recipe_ingredients::ingredient_id.ne_all(kept_ingredient_ids)),
+                )
+                .execute(conn)
+            )?;
+
             let zipped: Vec<(pg::RecipeIngredient, pg::Ingredient)> = inserted_recipe_ingredients
                 .iter()
                 .map(|ri| ri.clone())
@@ -176,4 +495,158 @@ impl RecipeRepository {
             ))
         })
     }
+
+    /// Create a recipe on behalf of the session user, recording them as the
+    /// owner. Unauthenticated callers are rejected before anything is written.
+    ///
+    /// Recipe names are globally unique, so the upsert in `insert_recipe` would
+    /// otherwise let a caller clobber — and seize ownership of — a recipe that
+    /// already belongs to someone else. Reject that up front: creating over a
+    /// name you do not own is not allowed.
+    pub fn create_recipe(
+        conn: &PgConnection,
+        recipe: gql::NewRecipe,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<gql::Recipe> {
+        let user = authenticate(conn, username, session_token)?;
+
+        let existing_owner: Option<Option<i32>> = as_field_result(
+            recipes::table
+                .filter(recipes::name.eq(&recipe.name))
+                .select(recipes::user_id)
+                .get_result::<Option<i32>>(conn)
+                .optional()
+        )?;
+        if let Some(owner) = existing_owner {
+            if owner != Some(user.id) {
+                return Err(FieldError::new(
+                    format!("A recipe named {} already exists", recipe.name),
+                    Value::null(),
+                ));
+            }
+        }
+
+        Self::insert_recipe(conn, recipe, user.id)
+    }
+
+    /// Upsert an existing recipe, but only when the session user already owns
+    /// the row under that name. A recipe owned by somebody else (or by nobody)
+    /// is left untouched.
+    pub fn update_recipe(
+        conn: &PgConnection,
+        recipe: gql::NewRecipe,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<gql::Recipe> {
+        let user = authenticate(conn, username, session_token)?;
+        Self::owned_recipe(conn, &recipe.name, user.id)?;
+        Self::insert_recipe(conn, recipe, user.id)
+    }
+
+    /// Delete a recipe owned by the session user, returning whether a row was
+    /// removed. Callers who do not own the recipe are rejected.
+    pub fn delete_recipe(
+        conn: &PgConnection,
+        recipe_name: &str,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<bool> {
+        let user = authenticate(conn, username, session_token)?;
+        let recipe = Self::owned_recipe(conn, recipe_name, user.id)?;
+
+        conn.transaction(|| {
+            // Clear every row that foreign-keys the recipe before the recipe
+            // itself, otherwise the final delete trips a raw FK violation.
+            as_field_result(
+                diesel::delete(
+                    recipe_ingredients::table
+                        .filter(recipe_ingredients::recipe_id.eq(recipe.id)),
+                )
+                .execute(conn),
+            )?;
+            as_field_result(
+                diesel::delete(
+                    recipe_shares::table.filter(recipe_shares::recipe_id.eq(recipe.id)),
+                )
+                .execute(conn),
+            )?;
+            as_field_result(
+                diesel::delete(
+                    recipe_translations::table
+                        .filter(recipe_translations::recipe_id.eq(recipe.id)),
+                )
+                .execute(conn),
+            )?;
+            as_field_result(
+                diesel::delete(
+                    recipe_dependencies::table.filter(
+                        recipe_dependencies::parent_recipe_id
+                            .eq(recipe.id)
+                            .or(recipe_dependencies::child_recipe_id.eq(recipe.id)),
+                    ),
+                )
+                .execute(conn),
+            )?;
+            let deleted = as_field_result(
+                diesel::delete(recipes::table.find(recipe.id)).execute(conn),
+            )?;
+            Ok(deleted > 0)
+        })
+    }
+
+    /// List the recipes owned by the session user.
+    pub fn get_recipes_by_user(
+        conn: &PgConnection,
+        username: Option<&str>,
+        session_token: Option<&str>,
+    ) -> FieldResult<Vec<gql::Recipe>> {
+        let user = authenticate(conn, username, session_token)?;
+
+        let pg_recipes: Vec<pg::Recipe> = as_field_result(
+            recipes::table
+                .filter(recipes::user_id.eq(user.id))
+                .get_results::<pg::Recipe>(conn)
+        )?;
+
+        let pg_recipes_with_ingredients = pg::RecipeIngredient::belonging_to(&pg_recipes)
+            .inner_join(ingredients::table)
+            .get_results::<(pg::RecipeIngredient, pg::Ingredient)>(conn)?
+            .grouped_by(&pg_recipes);
+
+        Ok(pg_recipes
+            .iter()
+            .zip(pg_recipes_with_ingredients)
+            .map(|(r, ings)| gql::Recipe::from_pg(r, &ings))
+            .collect())
+    }
+
+    /// Load a recipe by name and assert `user_id` owns it. A missing recipe and
+    /// a foreign owner are each reported as a `FieldError`, with distinct
+    /// messages.
+    fn owned_recipe(
+        conn: &PgConnection,
+        recipe_name: &str,
+        user_id: i32,
+    ) -> FieldResult<pg::Recipe> {
+        let recipe = as_field_result(
+            recipes::table
+                .filter(recipes::name.eq(recipe_name))
+                .get_result::<pg::Recipe>(conn)
+                .optional()
+        )?
+        .ok_or_else(|| FieldError::new(
+            format!("No recipe with name {}", recipe_name),
+            Value::null(),
+        ))?;
+
+        if recipe.user_id != Some(user_id) {
+            return Err(FieldError::new(
+                format!("Not allowed to modify recipe {}", recipe_name),
+                Value::null(),
+            ));
+        }
+
+        Ok(recipe)
+    }
 }
\ No newline at end of file
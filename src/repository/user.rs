@@ -0,0 +1,200 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration, Utc};
+use diesel::PgConnection;
+use diesel::prelude::*;
+use juniper::{FieldError, FieldResult, Value};
+
+use crate::db::PostgresPool;
+use crate::graphql::RegistrationResult;
+use crate::models::postgres as pg;
+use crate::repository::auth::as_field_result;
+use crate::schema::*;
+use crate::session::{self, SESSION_TTL_SECONDS};
+
+/// Hash a plaintext password with Argon2id, returning the PHC string (which
+/// embeds the algorithm, parameters and a freshly generated salt) that is
+/// stored verbatim in the `password` column.
+fn hash_password(password: &str) -> FieldResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| FieldError::new(format!("Could not hash password: {}", e), Value::null()))
+}
+
+/// A random base64 token (characters `A-Z`, `a-z`, `0-9`, `+` and `/`, never
+/// `.`) used both for the session cookie and for the one-shot account
+/// validation link.
+fn random_token() -> String {
+    SaltString::generate(&mut OsRng).as_str().to_string()
+}
+
+pub struct UserRepository {
+    pub pool: PostgresPool
+}
+
+impl UserRepository {
+    /// Register a new account. The password is stored as an Argon2id PHC
+    /// string, never in plaintext, and the user starts out unvalidated with a
+    /// random `validation_token` that [`validate_account`](Self::validate_account)
+    /// later clears. A clashing username or email short-circuits without
+    /// writing anything.
+    pub fn register(
+        conn: &PgConnection,
+        username: &str,
+        email: &str,
+        password: &str,
+    ) -> FieldResult<RegistrationResult> {
+        // The session cookie is `username.token.hmac`, split on `.`, so a
+        // username must not contain the delimiter (nor be empty). Constrain it
+        // to a conservative identifier charset rather than risk a user who can
+        // never hold a session.
+        let username_ok = !username.is_empty()
+            && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !username_ok {
+            return Err(FieldError::new(
+                "Username may only contain letters, digits, '_' and '-'",
+                Value::null(),
+            ));
+        }
+
+        let username_taken = as_field_result(
+            users::table
+                .filter(users::username.eq(username))
+                .select(users::id)
+                .get_result::<i32>(conn)
+                .optional()
+        )?
+        .is_some();
+        if username_taken {
+            return Ok(RegistrationResult::UsernameTaken);
+        }
+
+        let email_taken = as_field_result(
+            users::table
+                .filter(users::email.eq(email))
+                .select(users::id)
+                .get_result::<i32>(conn)
+                .optional()
+        )?
+        .is_some();
+        if email_taken {
+            return Ok(RegistrationResult::EmailTaken);
+        }
+
+        let new_user = pg::NewUser {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: hash_password(password)?,
+            validated: false,
+            validation_token: Some(random_token()),
+        };
+
+        as_field_result(
+            diesel::insert_into(users::table)
+                .values(new_user)
+                .execute(conn)
+        )?;
+
+        Ok(RegistrationResult::Ok)
+    }
+
+    /// Flip the account carrying `token` to validated and clear the token so it
+    /// cannot be replayed. An unknown token is reported as a `FieldError`.
+    pub fn validate_account(conn: &PgConnection, token: &str) -> FieldResult<bool> {
+        let updated = as_field_result(
+            diesel::update(users::table.filter(users::validation_token.eq(token)))
+                .set((
+                    users::validated.eq(true),
+                    users::validation_token.eq::<Option<String>>(None),
+                ))
+                .execute(conn)
+        )?;
+
+        if updated == 0 {
+            return Err(FieldError::new("Invalid validation token", Value::null()));
+        }
+
+        Ok(true)
+    }
+
+    /// Verify the credentials and, on success, open a session and hand back the
+    /// signed `username.token.hmac` cookie value. Accounts that have not yet
+    /// been validated are rejected even when the password matches.
+    pub fn try_login(
+        conn: &PgConnection,
+        username: &str,
+        password: &str,
+    ) -> Option<String> {
+        let user = users::table
+            .filter(users::username.eq(username))
+            .get_result::<pg::User>(conn)
+            .optional()
+            .ok()
+            .flatten()?;
+
+        if !user.validated {
+            return None;
+        }
+
+        let parsed = PasswordHash::new(&user.password).ok()?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .ok()?;
+
+        let token = random_token();
+        let expires_at = Utc::now().naive_utc() + Duration::seconds(SESSION_TTL_SECONDS);
+        diesel::insert_into(sessions::table)
+            .values(pg::NewSession {
+                user_id: user.id,
+                token: token.clone(),
+                expires_at,
+            })
+            .execute(conn)
+            .ok()?;
+
+        Some(session::cookie_value(&user.username, &token, expires_at.timestamp()))
+    }
+
+    /// Resolve the user behind a `username.token.hmac` cookie, verifying the
+    /// signature and expiry. A tampered signature, an unknown token, or an
+    /// expired session yields `None`; an expired session is deleted on the way
+    /// out so it cannot be retried.
+    pub fn resolve_session(
+        conn: &PgConnection,
+        username: &str,
+        token: &str,
+        signature: &str,
+    ) -> Option<pg::User> {
+        let stored = sessions::table
+            .filter(sessions::token.eq(token))
+            .get_result::<pg::Session>(conn)
+            .optional()
+            .ok()
+            .flatten()?;
+
+        if !session::verify(username, token, stored.expires_at.timestamp(), signature) {
+            return None;
+        }
+
+        if stored.expires_at <= Utc::now().naive_utc() {
+            let _ = diesel::delete(sessions::table.find(stored.id)).execute(conn);
+            return None;
+        }
+
+        let user = users::table
+            .find(stored.user_id)
+            .get_result::<pg::User>(conn)
+            .optional()
+            .ok()
+            .flatten()?;
+
+        if user.username != username {
+            return None;
+        }
+
+        Some(user)
+    }
+}
@@ -45,20 +45,26 @@ fn get_context(pool: PostgresPool) -> BoxedFilter<(Context, )> {
     warp::any()
         .and(warp::cookie::optional("User-Session-Token"))
         .map(move |token_cookie: Option<String>| {
-            let (user, token) = match token_cookie {
-                Some(c) => parse_session_cookie(c),
-                _ => (None, None)
-            };
+            let (username, token) = token_cookie
+                .and_then(|c| verify_session_cookie(&pool, &c))
+                .map_or((None, None), |(user, token)| (Some(user), Some(token)));
 
-            Context { pool: pool.clone(), username: user, session_token: token }
+            Context { pool: pool.clone(), username, session_token: token }
         })
         .boxed()
 }
 
-fn parse_session_cookie(token_cookie: String) -> (Option<String>, Option<String>) {
-    let mut splitted = token_cookie.split("##");
-    match (splitted.nth(0), splitted.nth(0)) {
-        (Some(user), Some(token)) => (Some(user.into()), Some(token.into())),
-        _ => (None, None)
-    }
+/// Split a `username.token.hmac` cookie and hand it to the session store for
+/// signature and expiry verification. Anything malformed, forged or expired
+/// collapses to `None`, so `get_context` falls back to an anonymous context.
+fn verify_session_cookie(pool: &PostgresPool, cookie: &str) -> Option<(String, String)> {
+    let mut parts = cookie.splitn(3, '.');
+    let (username, token, signature) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(username), Some(token), Some(signature)) => (username, token, signature),
+        _ => return None,
+    };
+
+    let conn = pool.get().ok()?;
+    UserRepository::resolve_session(&conn, username, token, signature)
+        .map(|user| (user.username, token.to_string()))
 }
@@ -0,0 +1,62 @@
+//! Signed, expiring session cookies.
+//!
+//! The cookie carried by the client is `username.token.hmac`, where `hmac` is
+//! a hex-encoded HMAC-SHA256 over `username|token|expires_at` keyed by a server
+//! secret. The random `token` and its `expires_at` live in the `sessions`
+//! table; `route::get_context` looks the token up there and checks the
+//! signature against the stored expiry, rejecting a forged or tampered cookie,
+//! while the stored expiry bounds a leaked cookie's lifetime.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Environment variable holding the secret the HMAC is keyed with.
+const SECRET_ENV: &str = "SESSION_SECRET";
+
+/// How long a freshly minted session stays valid.
+pub const SESSION_TTL_SECONDS: i64 = 60 * 60 * 24 * 7;
+
+fn secret() -> Vec<u8> {
+    let secret = std::env::var(SECRET_ENV).unwrap_or_default();
+    // An empty key would make every signature forgeable, defeating the whole
+    // point of signing the cookie; refuse to run rather than fall back to it.
+    assert!(
+        !secret.is_empty(),
+        "{} must be set to a non-empty server secret",
+        SECRET_ENV,
+    );
+    secret.into_bytes()
+}
+
+fn signing_payload(username: &str, token: &str, expires_at: i64) -> String {
+    format!("{}|{}|{}", username, token, expires_at)
+}
+
+/// Hex-encoded HMAC over `username|token|expires_at`.
+pub fn sign(username: &str, token: &str, expires_at: i64) -> String {
+    let mut mac = HmacSha256::new_from_slice(&secret())
+        .expect("HMAC accepts keys of any length");
+    mac.update(signing_payload(username, token, expires_at).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Constant-time check that `signature` is the HMAC we would have produced for
+/// these values. Verification goes through the `hmac` crate's own comparison so
+/// a mismatch leaks no timing information.
+pub fn verify(username: &str, token: &str, expires_at: i64, signature: &str) -> bool {
+    let raw = match hex::decode(signature) {
+        Ok(raw) => raw,
+        Err(_) => return false,
+    };
+    let mut mac = HmacSha256::new_from_slice(&secret())
+        .expect("HMAC accepts keys of any length");
+    mac.update(signing_payload(username, token, expires_at).as_bytes());
+    mac.verify_slice(&raw).is_ok()
+}
+
+/// Assemble the `username.token.hmac` cookie value set on login.
+pub fn cookie_value(username: &str, token: &str, expires_at: i64) -> String {
+    format!("{}.{}.{}", username, token, sign(username, token, expires_at))
+}